@@ -0,0 +1,182 @@
+use crate::PgPool;
+use anyhow::Result;
+use async_trait::async_trait;
+use chrono::NaiveDateTime;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use uuid::Uuid;
+
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Clone, Copy, sqlx::Type)]
+#[serde(rename_all = "snake_case")]
+#[sqlx(type_name = "job_status", rename_all = "snake_case")]
+pub enum JobStatus {
+    New,
+    Running,
+}
+
+#[derive(Debug, PartialEq, Clone, sqlx::FromRow)]
+pub struct Job {
+    pub id: Uuid,
+    pub queue: String,
+    pub job: Value,
+    pub status: JobStatus,
+    pub attempts: i32,
+    pub created_at: NaiveDateTime,
+    pub heartbeat: Option<NaiveDateTime>,
+}
+
+/// A durable, at-least-once job queue backed by the `job_queue` table.
+///
+/// Jobs are claimed with `SELECT ... FOR UPDATE SKIP LOCKED` so multiple
+/// workers can poll the same queue concurrently without double-processing a
+/// row, and a stale `running` job (its worker died before finishing) can be
+/// handed back out via [`JobQueue::requeue_stale_jobs`].
+#[async_trait]
+pub trait JobQueue {
+    async fn enqueue(&self, queue: &str, job: Value) -> Result<Uuid>;
+    async fn poll_next(&self, queue: &str) -> Result<Option<Job>>;
+    async fn requeue_stale_jobs(&self, queue: &str, heartbeat_timeout_second: i64) -> Result<u64>;
+    async fn complete(&self, id: Uuid) -> Result<()>;
+}
+
+#[async_trait]
+impl JobQueue for PgPool {
+    async fn enqueue(&self, queue: &str, job: Value) -> Result<Uuid> {
+        let id = Uuid::new_v4();
+        sqlx::query(
+            r"
+            INSERT INTO job_queue (id, queue, job, status, attempts)
+            VALUES ($1, $2, $3, 'new', 0)
+            ",
+        )
+        .bind(id)
+        .bind(queue)
+        .bind(job)
+        .execute(self)
+        .await?;
+        Ok(id)
+    }
+
+    async fn poll_next(&self, queue: &str) -> Result<Option<Job>> {
+        let mut tx = self.begin().await?;
+
+        let job: Option<Job> = sqlx::query_as(
+            r"
+            SELECT id, queue, job, status, attempts, created_at, heartbeat
+            FROM job_queue
+            WHERE queue = $1
+            AND status = 'new'
+            ORDER BY created_at ASC
+            LIMIT 1
+            FOR UPDATE SKIP LOCKED
+            ",
+        )
+        .bind(queue)
+        .fetch_optional(&mut tx)
+        .await?;
+
+        let job = match job {
+            Some(job) => job,
+            None => {
+                tx.commit().await?;
+                return Ok(None);
+            }
+        };
+
+        sqlx::query(
+            r"
+            UPDATE job_queue
+            SET status = 'running', attempts = attempts + 1, heartbeat = NOW()
+            WHERE id = $1
+            ",
+        )
+        .bind(job.id)
+        .execute(&mut tx)
+        .await?;
+
+        tx.commit().await?;
+
+        Ok(Some(Job {
+            status: JobStatus::Running,
+            attempts: job.attempts + 1,
+            ..job
+        }))
+    }
+
+    async fn requeue_stale_jobs(&self, queue: &str, heartbeat_timeout_second: i64) -> Result<u64> {
+        let result = sqlx::query(
+            r"
+            UPDATE job_queue
+            SET status = 'new', heartbeat = NULL
+            WHERE queue = $1
+            AND status = 'running'
+            AND heartbeat < NOW() - make_interval(secs => $2)
+            ",
+        )
+        .bind(queue)
+        .bind(heartbeat_timeout_second as f64)
+        .execute(self)
+        .await?;
+
+        Ok(result.rows_affected())
+    }
+
+    async fn complete(&self, id: Uuid) -> Result<()> {
+        sqlx::query("DELETE FROM job_queue WHERE id = $1")
+            .bind(id)
+            .execute(self)
+            .await?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[sqlx::test(migrations = "./migrations")]
+    async fn poll_next_claims_jobs_oldest_first(pool: PgPool) {
+        let first = pool.enqueue("q", json!({"n": 1})).await.unwrap();
+        let second = pool.enqueue("q", json!({"n": 2})).await.unwrap();
+
+        let claimed = pool.poll_next("q").await.unwrap().unwrap();
+        assert_eq!(claimed.id, first);
+        assert_eq!(claimed.status, JobStatus::Running);
+        assert_eq!(claimed.attempts, 1);
+
+        let claimed = pool.poll_next("q").await.unwrap().unwrap();
+        assert_eq!(claimed.id, second);
+
+        assert!(pool.poll_next("q").await.unwrap().is_none());
+    }
+
+    #[sqlx::test(migrations = "./migrations")]
+    async fn requeue_stale_jobs_hands_a_dead_workers_job_back_out(pool: PgPool) {
+        let id = pool.enqueue("q", json!({})).await.unwrap();
+        pool.poll_next("q").await.unwrap();
+
+        assert_eq!(pool.requeue_stale_jobs("q", 3600).await.unwrap(), 0);
+
+        sqlx::query("UPDATE job_queue SET heartbeat = NOW() - INTERVAL '1 hour' WHERE id = $1")
+            .bind(id)
+            .execute(&pool)
+            .await
+            .unwrap();
+
+        assert_eq!(pool.requeue_stale_jobs("q", 60).await.unwrap(), 1);
+
+        let requeued = pool.poll_next("q").await.unwrap().unwrap();
+        assert_eq!(requeued.id, id);
+        assert_eq!(requeued.attempts, 2);
+    }
+
+    #[sqlx::test(migrations = "./migrations")]
+    async fn complete_deletes_the_row(pool: PgPool) {
+        let id = pool.enqueue("q", json!({})).await.unwrap();
+
+        pool.complete(id).await.unwrap();
+
+        assert!(pool.poll_next("q").await.unwrap().is_none());
+    }
+}
@@ -0,0 +1,178 @@
+use crate::internal::virtual_contest_manager::{
+    ContestFilters, VirtualContestInfo, VirtualContestItem, VirtualContestManager,
+    VirtualContestMode,
+};
+use crate::PgPool;
+use anyhow::Result;
+use async_trait::async_trait;
+use metrics::{counter, histogram};
+use std::future::Future;
+use std::time::Instant;
+
+async fn with_metrics<F, T>(operation: &'static str, fut: F) -> Result<T>
+where
+    F: Future<Output = Result<T>>,
+{
+    let start = Instant::now();
+    let result = fut.await;
+    histogram!("virtual_contest_manager_duration_seconds", "operation" => operation)
+        .record(start.elapsed().as_secs_f64());
+    counter!("virtual_contest_manager_calls_total", "operation" => operation).increment(1);
+    if result.is_err() {
+        counter!("virtual_contest_manager_errors_total", "operation" => operation).increment(1);
+    }
+    result
+}
+
+/// A thin wrapper over [`PgPool`] that records a per-operation timing
+/// histogram and call/error counters (via the `metrics` crate facade) around
+/// every [`VirtualContestManager`] method, so a Prometheus exporter can
+/// surface p99 latencies and error rates for these join-heavy queries.
+///
+/// Drop-in replacement for [`PgPool`] at any call site that only needs the
+/// [`VirtualContestManager`] trait.
+pub struct MeasuredVirtualContestManager {
+    pool: PgPool,
+}
+
+impl MeasuredVirtualContestManager {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+}
+
+#[async_trait]
+impl VirtualContestManager for MeasuredVirtualContestManager {
+    async fn create_contest(
+        &self,
+        title: &str,
+        memo: &str,
+        internal_user_id: &str,
+        start_epoch_second: i64,
+        duration_second: i64,
+        mode: Option<VirtualContestMode>,
+        is_public: bool,
+        penalty_second: i64,
+    ) -> Result<String> {
+        with_metrics(
+            "create_contest",
+            self.pool.create_contest(
+                title,
+                memo,
+                internal_user_id,
+                start_epoch_second,
+                duration_second,
+                mode,
+                is_public,
+                penalty_second,
+            ),
+        )
+        .await
+    }
+
+    async fn update_contest(
+        &self,
+        id: &str,
+        title: &str,
+        memo: &str,
+        start_epoch_second: i64,
+        duration_second: i64,
+        mode: Option<VirtualContestMode>,
+        is_public: bool,
+        penalty_second: i64,
+    ) -> Result<()> {
+        with_metrics(
+            "update_contest",
+            self.pool.update_contest(
+                id,
+                title,
+                memo,
+                start_epoch_second,
+                duration_second,
+                mode,
+                is_public,
+                penalty_second,
+            ),
+        )
+        .await
+    }
+
+    async fn get_own_contests(&self, internal_user_id: &str) -> Result<Vec<VirtualContestInfo>> {
+        with_metrics("get_own_contests", self.pool.get_own_contests(internal_user_id)).await
+    }
+
+    async fn get_participated_contests(
+        &self,
+        internal_user_id: &str,
+    ) -> Result<Vec<VirtualContestInfo>> {
+        with_metrics(
+            "get_participated_contests",
+            self.pool.get_participated_contests(internal_user_id),
+        )
+        .await
+    }
+
+    async fn get_single_contest_info(&self, contest_id: &str) -> Result<VirtualContestInfo> {
+        with_metrics(
+            "get_single_contest_info",
+            self.pool.get_single_contest_info(contest_id),
+        )
+        .await
+    }
+
+    async fn get_single_contest_participants(&self, contest_id: &str) -> Result<Vec<String>> {
+        with_metrics(
+            "get_single_contest_participants",
+            self.pool.get_single_contest_participants(contest_id),
+        )
+        .await
+    }
+
+    async fn get_single_contest_problems(
+        &self,
+        contest_id: &str,
+    ) -> Result<Vec<VirtualContestItem>> {
+        with_metrics(
+            "get_single_contest_problems",
+            self.pool.get_single_contest_problems(contest_id),
+        )
+        .await
+    }
+
+    async fn get_recent_contest_info(&self) -> Result<Vec<VirtualContestInfo>> {
+        with_metrics("get_recent_contest_info", self.pool.get_recent_contest_info()).await
+    }
+
+    async fn list_contests(&self, filters: ContestFilters) -> Result<Vec<VirtualContestInfo>> {
+        with_metrics("list_contests", self.pool.list_contests(filters)).await
+    }
+
+    async fn get_running_contest_problems(&self, time: i64) -> Result<Vec<(String, i64)>> {
+        with_metrics(
+            "get_running_contest_problems",
+            self.pool.get_running_contest_problems(time),
+        )
+        .await
+    }
+
+    async fn update_items(
+        &self,
+        contest_id: &str,
+        problems: &[VirtualContestItem],
+        user_id: &str,
+    ) -> Result<()> {
+        with_metrics(
+            "update_items",
+            self.pool.update_items(contest_id, problems, user_id),
+        )
+        .await
+    }
+
+    async fn join_contest(&self, contest_id: &str, internal_user_id: &str) -> Result<()> {
+        with_metrics("join_contest", self.pool.join_contest(contest_id, internal_user_id)).await
+    }
+
+    async fn leave_contest(&self, contest_id: &str, internal_user_id: &str) -> Result<()> {
+        with_metrics("leave_contest", self.pool.leave_contest(contest_id, internal_user_id)).await
+    }
+}
@@ -0,0 +1,10 @@
+mod job_queue;
+mod metrics;
+mod virtual_contest_manager;
+
+pub use job_queue::{Job, JobQueue, JobStatus};
+pub use metrics::MeasuredVirtualContestManager;
+pub use virtual_contest_manager::{
+    ContestFilters, DedupingVirtualContestManager, VirtualContestInfo, VirtualContestItem,
+    VirtualContestManager, VirtualContestMode, MAX_PROBLEM_NUM_PER_CONTEST, RECENT_CONTEST_NUM,
+};
@@ -1,13 +1,27 @@
 use crate::PgPool;
 use anyhow::{ensure, Context, Result};
 use async_trait::async_trait;
+use dashmap::DashMap;
 use serde::{Deserialize, Serialize};
-use sqlx::Row;
+use sqlx::{Postgres, QueryBuilder, Row};
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+use std::sync::Arc;
 use uuid::Uuid;
 
 pub const MAX_PROBLEM_NUM_PER_CONTEST: usize = 300;
 pub const RECENT_CONTEST_NUM: i64 = 1000;
 
+/// Mirrors the `virtual_contest_mode` Postgres ENUM created in the migrations.
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Clone, Copy, sqlx::Type)]
+#[serde(rename_all = "snake_case")]
+#[sqlx(type_name = "virtual_contest_mode", rename_all = "snake_case")]
+pub enum VirtualContestMode {
+    Normal,
+    Lockout,
+    Training,
+}
+
 #[derive(Serialize, Debug, PartialEq, Eq, Clone, sqlx::FromRow)]
 pub struct VirtualContestInfo {
     pub id: String,
@@ -17,11 +31,37 @@ pub struct VirtualContestInfo {
     pub owner_user_id: String, // column name is `internal_user_id`
     pub start_epoch_second: i64,
     pub duration_second: i64,
-    pub mode: Option<String>,
+    pub mode: Option<VirtualContestMode>,
     pub is_public: bool,
     pub penalty_second: i64,
 }
 
+/// Optional filters for [`VirtualContestManager::list_contests`].
+///
+/// Every filter is opt-in: a clause is only appended to the generated query
+/// when the corresponding field is `Some` (or `true`, for the `bool` flags), so
+/// `ContestFilters::default()` lists every public contest (no `LIMIT`/`OFFSET`
+/// included at all).
+#[derive(Debug, Default, PartialEq, Eq, Clone)]
+pub struct ContestFilters {
+    /// Case-insensitive substring match against `title` OR `memo`.
+    pub text: Option<String>,
+    pub mode: Option<VirtualContestMode>,
+    pub owner_user_id: Option<String>,
+    /// Only contests starting at or after this epoch second.
+    pub after: Option<i64>,
+    /// Only contests starting at or before this epoch second.
+    pub before: Option<i64>,
+    /// Only contests that are running at the current time, i.e.
+    /// `start_epoch_second <= now <= start_epoch_second + duration_second`.
+    pub only_running: bool,
+    pub limit: Option<i64>,
+    pub offset: Option<i64>,
+    /// When `true`, order by `start_epoch_second + duration_second` ascending
+    /// instead of the default descending order.
+    pub reverse: bool,
+}
+
 #[derive(Serialize, Deserialize, Debug, PartialEq, Eq, sqlx::FromRow)]
 pub struct VirtualContestItem {
     #[sqlx(rename = "problem_id")]
@@ -32,6 +72,90 @@ pub struct VirtualContestItem {
     pub order: Option<i64>, // column name is `user_defined_order`
 }
 
+/// Escapes the `ILIKE` wildcard characters (`%`, `_`) and the escape
+/// character itself (`\`) in `text`, so a literal substring search doesn't
+/// let user input widen the match (e.g. a search for `100%` would otherwise
+/// match any title starting with `100`).
+fn escape_like_pattern(text: &str) -> String {
+    text.replace('\\', "\\\\")
+        .replace('%', "\\%")
+        .replace('_', "\\_")
+}
+
+/// Builds the `SELECT` for [`VirtualContestManager::list_contests`],
+/// appending a clause per `Some` field of `filters`. Kept as a standalone
+/// function (rather than inlined into the trait method) so its generated SQL
+/// can be asserted on in tests without a database connection.
+///
+/// Always restricted to `is_public IS TRUE`: this is the public contest
+/// discovery path, so private contests should never leak through it (use
+/// [`VirtualContestManager::get_own_contests`] or
+/// [`VirtualContestManager::get_participated_contests`] for a caller's own
+/// private contests).
+fn build_list_contests_query(filters: &ContestFilters) -> QueryBuilder<'static, Postgres> {
+    let mut builder: QueryBuilder<Postgres> = QueryBuilder::new(
+        r"
+        SELECT
+            id,
+            title,
+            memo,
+            internal_user_id,
+            start_epoch_second,
+            duration_second,
+            mode,
+            is_public,
+            penalty_second
+        FROM internal_virtual_contests
+        WHERE is_public IS TRUE
+        ",
+    );
+
+    if let Some(text) = &filters.text {
+        let pattern = format!("%{}%", escape_like_pattern(text));
+        builder
+            .push(" AND (title ILIKE ")
+            .push_bind(pattern.clone())
+            .push(" OR memo ILIKE ")
+            .push_bind(pattern)
+            .push(")");
+    }
+    if let Some(mode) = filters.mode {
+        builder.push(" AND mode = ").push_bind(mode);
+    }
+    if let Some(owner_user_id) = &filters.owner_user_id {
+        builder
+            .push(" AND internal_user_id = ")
+            .push_bind(owner_user_id.clone());
+    }
+    if let Some(after) = filters.after {
+        builder.push(" AND start_epoch_second >= ").push_bind(after);
+    }
+    if let Some(before) = filters.before {
+        builder.push(" AND start_epoch_second <= ").push_bind(before);
+    }
+    if filters.only_running {
+        builder.push(
+            " AND start_epoch_second <= EXTRACT(EPOCH FROM NOW())
+              AND start_epoch_second + duration_second >= EXTRACT(EPOCH FROM NOW())",
+        );
+    }
+
+    if filters.reverse {
+        builder.push(" ORDER BY start_epoch_second + duration_second ASC");
+    } else {
+        builder.push(" ORDER BY start_epoch_second + duration_second DESC");
+    }
+
+    if let Some(limit) = filters.limit {
+        builder.push(" LIMIT ").push_bind(limit);
+    }
+    if let Some(offset) = filters.offset {
+        builder.push(" OFFSET ").push_bind(offset);
+    }
+
+    builder
+}
+
 #[async_trait]
 pub trait VirtualContestManager {
     async fn create_contest(
@@ -41,7 +165,7 @@ pub trait VirtualContestManager {
         internal_user_id: &str,
         start_epoch_second: i64,
         duration_second: i64,
-        mode: Option<&str>,
+        mode: Option<VirtualContestMode>,
         is_public: bool,
         penalty_second: i64,
     ) -> Result<String>;
@@ -52,7 +176,7 @@ pub trait VirtualContestManager {
         memo: &str,
         start_epoch_second: i64,
         duration_second: i64,
-        mode: Option<&str>,
+        mode: Option<VirtualContestMode>,
         is_public: bool,
         penalty_second: i64,
     ) -> Result<()>;
@@ -69,6 +193,7 @@ pub trait VirtualContestManager {
         contest_id: &str,
     ) -> Result<Vec<VirtualContestItem>>;
     async fn get_recent_contest_info(&self) -> Result<Vec<VirtualContestInfo>>;
+    async fn list_contests(&self, filters: ContestFilters) -> Result<Vec<VirtualContestInfo>>;
     async fn get_running_contest_problems(&self, time: i64) -> Result<Vec<(String, i64)>>;
 
     async fn update_items(
@@ -91,7 +216,7 @@ impl VirtualContestManager for PgPool {
         internal_user_id: &str,
         start_epoch_second: i64,
         duration_second: i64,
-        mode: Option<&str>,
+        mode: Option<VirtualContestMode>,
         is_public: bool,
         penalty_second: i64,
     ) -> Result<String> {
@@ -123,7 +248,7 @@ impl VirtualContestManager for PgPool {
         memo: &str,
         start_epoch_second: i64,
         duration_second: i64,
-        mode: Option<&str>,
+        mode: Option<VirtualContestMode>,
         is_public: bool,
         penalty_second: i64,
     ) -> Result<()> {
@@ -303,6 +428,17 @@ impl VirtualContestManager for PgPool {
         Ok(contests)
     }
 
+    async fn list_contests(&self, filters: ContestFilters) -> Result<Vec<VirtualContestInfo>> {
+        let mut builder = build_list_contests_query(&filters);
+
+        let contests = builder
+            .build_query_as::<VirtualContestInfo>()
+            .fetch_all(self)
+            .await?;
+
+        Ok(contests)
+    }
+
     async fn get_running_contest_problems(&self, time: i64) -> Result<Vec<(String, i64)>> {
         let problems = sqlx::query(
             r"
@@ -432,3 +568,385 @@ impl VirtualContestManager for PgPool {
         Ok(())
     }
 }
+
+/// Digests the *entire* ordered batch `update_items` would write for a
+/// contest (not each item individually) — `update_items` replaces the whole
+/// set in one delete-then-reinsert, so the cache must compare against the
+/// full previously-written batch, or a later write that only drops items
+/// (a subset of an already-seen batch) would be wrongly skipped.
+fn virtual_contest_items_digest(contest_id: &str, items: &[VirtualContestItem]) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    contest_id.hash(&mut hasher);
+    for item in items {
+        item.id.hash(&mut hasher);
+        item.point.hash(&mut hasher);
+        item.order.hash(&mut hasher);
+    }
+    hasher.finish()
+}
+
+/// Wraps a [`VirtualContestManager`] with an in-process, content-hash write
+/// skip for [`VirtualContestManager::update_items`]: when the batch for a
+/// contest is byte-for-byte identical (by content hash) to the last batch
+/// written for that same contest, the delete-then-reinsert round trip is
+/// skipped entirely.
+///
+/// The cache maps `contest_id -> digest of its last-written batch` via
+/// `Arc<DashMap<String, u64>>`, so it can be injected (a fresh empty map
+/// starts with no contest seen), and invalidated with [`Self::invalidate`]
+/// when a contest's items are known to have changed by some other path. The
+/// skip cannot be turned off: every write-through records its digest, so an
+/// identical repeat batch for the same contest is skipped.
+pub struct DedupingVirtualContestManager<'a, M: VirtualContestManager> {
+    inner: &'a M,
+    seen: Arc<DashMap<String, u64>>,
+}
+
+impl<'a, M: VirtualContestManager> DedupingVirtualContestManager<'a, M> {
+    pub fn new(inner: &'a M, seen: Arc<DashMap<String, u64>>) -> Self {
+        Self { inner, seen }
+    }
+
+    pub fn invalidate(&self, contest_id: &str) {
+        self.seen.remove(contest_id);
+    }
+}
+
+#[async_trait]
+impl<'a, M: VirtualContestManager + Sync> VirtualContestManager
+    for DedupingVirtualContestManager<'a, M>
+{
+    async fn create_contest(
+        &self,
+        title: &str,
+        memo: &str,
+        internal_user_id: &str,
+        start_epoch_second: i64,
+        duration_second: i64,
+        mode: Option<VirtualContestMode>,
+        is_public: bool,
+        penalty_second: i64,
+    ) -> Result<String> {
+        self.inner
+            .create_contest(
+                title,
+                memo,
+                internal_user_id,
+                start_epoch_second,
+                duration_second,
+                mode,
+                is_public,
+                penalty_second,
+            )
+            .await
+    }
+
+    async fn update_contest(
+        &self,
+        id: &str,
+        title: &str,
+        memo: &str,
+        start_epoch_second: i64,
+        duration_second: i64,
+        mode: Option<VirtualContestMode>,
+        is_public: bool,
+        penalty_second: i64,
+    ) -> Result<()> {
+        self.inner
+            .update_contest(
+                id,
+                title,
+                memo,
+                start_epoch_second,
+                duration_second,
+                mode,
+                is_public,
+                penalty_second,
+            )
+            .await
+    }
+
+    async fn get_own_contests(&self, internal_user_id: &str) -> Result<Vec<VirtualContestInfo>> {
+        self.inner.get_own_contests(internal_user_id).await
+    }
+
+    async fn get_participated_contests(
+        &self,
+        internal_user_id: &str,
+    ) -> Result<Vec<VirtualContestInfo>> {
+        self.inner.get_participated_contests(internal_user_id).await
+    }
+
+    async fn get_single_contest_info(&self, contest_id: &str) -> Result<VirtualContestInfo> {
+        self.inner.get_single_contest_info(contest_id).await
+    }
+
+    async fn get_single_contest_participants(&self, contest_id: &str) -> Result<Vec<String>> {
+        self.inner.get_single_contest_participants(contest_id).await
+    }
+
+    async fn get_single_contest_problems(
+        &self,
+        contest_id: &str,
+    ) -> Result<Vec<VirtualContestItem>> {
+        self.inner.get_single_contest_problems(contest_id).await
+    }
+
+    async fn get_recent_contest_info(&self) -> Result<Vec<VirtualContestInfo>> {
+        self.inner.get_recent_contest_info().await
+    }
+
+    async fn list_contests(&self, filters: ContestFilters) -> Result<Vec<VirtualContestInfo>> {
+        self.inner.list_contests(filters).await
+    }
+
+    async fn get_running_contest_problems(&self, time: i64) -> Result<Vec<(String, i64)>> {
+        self.inner.get_running_contest_problems(time).await
+    }
+
+    async fn update_items(
+        &self,
+        contest_id: &str,
+        problems: &[VirtualContestItem],
+        user_id: &str,
+    ) -> Result<()> {
+        let digest = virtual_contest_items_digest(contest_id, problems);
+        if self.seen.get(contest_id).map(|d| *d) == Some(digest) {
+            return Ok(());
+        }
+
+        self.inner.update_items(contest_id, problems, user_id).await?;
+        self.seen.insert(contest_id.to_string(), digest);
+        Ok(())
+    }
+
+    async fn join_contest(&self, contest_id: &str, internal_user_id: &str) -> Result<()> {
+        self.inner.join_contest(contest_id, internal_user_id).await
+    }
+
+    async fn leave_contest(&self, contest_id: &str, internal_user_id: &str) -> Result<()> {
+        self.inner.leave_contest(contest_id, internal_user_id).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Mutex;
+
+    /// A [`VirtualContestManager`] that only implements `update_items`,
+    /// recording every batch it is actually asked to write.
+    struct RecordingManager {
+        writes: Mutex<Vec<Vec<VirtualContestItem>>>,
+    }
+
+    impl RecordingManager {
+        fn new() -> Self {
+            Self {
+                writes: Mutex::new(vec![]),
+            }
+        }
+    }
+
+    #[async_trait]
+    impl VirtualContestManager for RecordingManager {
+        async fn create_contest(
+            &self,
+            _title: &str,
+            _memo: &str,
+            _internal_user_id: &str,
+            _start_epoch_second: i64,
+            _duration_second: i64,
+            _mode: Option<VirtualContestMode>,
+            _is_public: bool,
+            _penalty_second: i64,
+        ) -> Result<String> {
+            unimplemented!()
+        }
+        async fn update_contest(
+            &self,
+            _id: &str,
+            _title: &str,
+            _memo: &str,
+            _start_epoch_second: i64,
+            _duration_second: i64,
+            _mode: Option<VirtualContestMode>,
+            _is_public: bool,
+            _penalty_second: i64,
+        ) -> Result<()> {
+            unimplemented!()
+        }
+        async fn get_own_contests(&self, _internal_user_id: &str) -> Result<Vec<VirtualContestInfo>> {
+            unimplemented!()
+        }
+        async fn get_participated_contests(
+            &self,
+            _internal_user_id: &str,
+        ) -> Result<Vec<VirtualContestInfo>> {
+            unimplemented!()
+        }
+        async fn get_single_contest_info(&self, _contest_id: &str) -> Result<VirtualContestInfo> {
+            unimplemented!()
+        }
+        async fn get_single_contest_participants(&self, _contest_id: &str) -> Result<Vec<String>> {
+            unimplemented!()
+        }
+        async fn get_single_contest_problems(
+            &self,
+            _contest_id: &str,
+        ) -> Result<Vec<VirtualContestItem>> {
+            unimplemented!()
+        }
+        async fn get_recent_contest_info(&self) -> Result<Vec<VirtualContestInfo>> {
+            unimplemented!()
+        }
+        async fn list_contests(&self, _filters: ContestFilters) -> Result<Vec<VirtualContestInfo>> {
+            unimplemented!()
+        }
+        async fn get_running_contest_problems(&self, _time: i64) -> Result<Vec<(String, i64)>> {
+            unimplemented!()
+        }
+        async fn update_items(
+            &self,
+            _contest_id: &str,
+            problems: &[VirtualContestItem],
+            _user_id: &str,
+        ) -> Result<()> {
+            self.writes.lock().unwrap().push(
+                problems
+                    .iter()
+                    .map(|item| VirtualContestItem {
+                        id: item.id.clone(),
+                        point: item.point,
+                        order: item.order,
+                    })
+                    .collect(),
+            );
+            Ok(())
+        }
+        async fn join_contest(&self, _contest_id: &str, _internal_user_id: &str) -> Result<()> {
+            unimplemented!()
+        }
+        async fn leave_contest(&self, _contest_id: &str, _internal_user_id: &str) -> Result<()> {
+            unimplemented!()
+        }
+    }
+
+    fn item(id: &str) -> VirtualContestItem {
+        VirtualContestItem {
+            id: id.to_string(),
+            point: None,
+            order: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn update_items_writes_through_on_first_call() {
+        let recorder = RecordingManager::new();
+        let deduping = DedupingVirtualContestManager::new(&recorder, Arc::new(DashMap::new()));
+
+        deduping
+            .update_items("contest", &[item("a"), item("b")], "user")
+            .await
+            .unwrap();
+
+        assert_eq!(recorder.writes.lock().unwrap().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn update_items_skips_an_identical_repeat_write() {
+        let recorder = RecordingManager::new();
+        let deduping = DedupingVirtualContestManager::new(&recorder, Arc::new(DashMap::new()));
+
+        deduping
+            .update_items("contest", &[item("a"), item("b")], "user")
+            .await
+            .unwrap();
+        deduping
+            .update_items("contest", &[item("a"), item("b")], "user")
+            .await
+            .unwrap();
+
+        assert_eq!(recorder.writes.lock().unwrap().len(), 1);
+    }
+
+    /// Regression test: a batch that *shrinks* an already-seen one (every
+    /// item it contains was part of a prior write) must still be written
+    /// through, since `update_items` is a whole-set replace and the removed
+    /// items need to be deleted.
+    #[tokio::test]
+    async fn update_items_writes_through_when_batch_shrinks() {
+        let recorder = RecordingManager::new();
+        let deduping = DedupingVirtualContestManager::new(&recorder, Arc::new(DashMap::new()));
+
+        deduping
+            .update_items("contest", &[item("a"), item("b")], "user")
+            .await
+            .unwrap();
+        deduping
+            .update_items("contest", &[item("a")], "user")
+            .await
+            .unwrap();
+
+        let writes = recorder.writes.lock().unwrap();
+        assert_eq!(writes.len(), 2);
+        assert_eq!(writes[1], vec![item("a")]);
+    }
+
+    #[test]
+    fn default_filters_build_an_unfiltered_query_with_no_limit_or_offset() {
+        let sql = build_list_contests_query(&ContestFilters::default())
+            .sql()
+            .to_string();
+
+        assert!(!sql.contains("LIMIT"));
+        assert!(!sql.contains("OFFSET"));
+        assert!(!sql.contains("ILIKE"));
+        assert!(sql.contains("WHERE is_public IS TRUE"));
+    }
+
+    #[test]
+    fn list_contests_always_restricts_to_public_contests() {
+        let filters = ContestFilters {
+            owner_user_id: Some("user".to_string()),
+            ..ContestFilters::default()
+        };
+        let sql = build_list_contests_query(&filters).sql().to_string();
+
+        assert!(sql.contains("WHERE is_public IS TRUE"));
+    }
+
+    #[test]
+    fn escape_like_pattern_escapes_wildcard_characters() {
+        assert_eq!(escape_like_pattern("100%"), "100\\%");
+        assert_eq!(escape_like_pattern("a_b"), "a\\_b");
+        assert_eq!(escape_like_pattern("a\\b"), "a\\\\b");
+        assert_eq!(escape_like_pattern("plain"), "plain");
+    }
+
+    #[test]
+    fn filters_are_only_appended_when_set() {
+        let filters = ContestFilters {
+            text: Some("abc".to_string()),
+            mode: Some(VirtualContestMode::Lockout),
+            owner_user_id: Some("user".to_string()),
+            after: Some(1),
+            before: Some(2),
+            only_running: true,
+            limit: Some(10),
+            offset: Some(20),
+            reverse: true,
+        };
+        let sql = build_list_contests_query(&filters).sql().to_string();
+
+        assert!(sql.contains("ILIKE"));
+        assert!(sql.contains("mode ="));
+        assert!(sql.contains("internal_user_id ="));
+        assert!(sql.contains("start_epoch_second >="));
+        assert!(sql.contains("start_epoch_second <="));
+        assert!(sql.contains("EXTRACT(EPOCH FROM NOW())"));
+        assert!(sql.contains("ORDER BY start_epoch_second + duration_second ASC"));
+        assert!(sql.contains("LIMIT"));
+        assert!(sql.contains("OFFSET"));
+    }
+}
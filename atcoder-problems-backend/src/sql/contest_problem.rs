@@ -1,9 +1,15 @@
 use crate::sql::models::ContestProblem;
 use crate::sql::schema::contest_problem;
 
+use dashmap::DashSet;
 use diesel::dsl::*;
 use diesel::prelude::*;
 use diesel::{PgConnection, QueryResult};
+use metrics::{counter, histogram};
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+use std::sync::Arc;
+use std::time::Instant;
 
 pub trait ContestProblemClient {
     fn insert_contest_problem(&self, contest_problems: &[ContestProblem]) -> QueryResult<usize>;
@@ -23,3 +29,98 @@ impl ContestProblemClient for PgConnection {
         contest_problem::table.load::<ContestProblem>(self)
     }
 }
+
+fn contest_problem_digest(contest_problem: &ContestProblem) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    contest_problem.contest_id.hash(&mut hasher);
+    contest_problem.problem_id.hash(&mut hasher);
+    hasher.finish()
+}
+
+/// Wraps a [`ContestProblemClient`] with an in-process, content-hash write
+/// skip: a bulk insert whose every element has already been seen (by content
+/// hash) is dropped before it reaches the database.
+///
+/// The cache is shared via `Arc<DashSet<u64>>` so it can be cloned across
+/// callers/threads, and can be invalidated with [`Self::invalidate`] when a
+/// contest's problems are known to have changed out from under it. A fresh,
+/// empty `DashSet` starts with nothing seen, but the skip cannot be turned
+/// off: every write-through records its digests, so a later identical batch
+/// is still dropped.
+pub struct DedupingContestProblemClient<'a, C: ContestProblemClient> {
+    inner: &'a C,
+    seen: Arc<DashSet<u64>>,
+}
+
+impl<'a, C: ContestProblemClient> DedupingContestProblemClient<'a, C> {
+    pub fn new(inner: &'a C, seen: Arc<DashSet<u64>>) -> Self {
+        Self { inner, seen }
+    }
+
+    pub fn invalidate(&self) {
+        self.seen.clear();
+    }
+}
+
+impl<'a, C: ContestProblemClient> ContestProblemClient for DedupingContestProblemClient<'a, C> {
+    fn insert_contest_problem(&self, contest_problems: &[ContestProblem]) -> QueryResult<usize> {
+        let digests = contest_problems
+            .iter()
+            .map(contest_problem_digest)
+            .collect::<Vec<_>>();
+        if !digests.is_empty() && digests.iter().all(|digest| self.seen.contains(digest)) {
+            return Ok(0);
+        }
+
+        let inserted = self.inner.insert_contest_problem(contest_problems)?;
+        for digest in digests {
+            self.seen.insert(digest);
+        }
+        Ok(inserted)
+    }
+
+    fn load_contest_problem(&self) -> QueryResult<Vec<ContestProblem>> {
+        self.inner.load_contest_problem()
+    }
+}
+
+fn with_metrics<T>(
+    operation: &'static str,
+    f: impl FnOnce() -> QueryResult<T>,
+) -> QueryResult<T> {
+    let start = Instant::now();
+    let result = f();
+    histogram!("contest_problem_client_duration_seconds", "operation" => operation)
+        .record(start.elapsed().as_secs_f64());
+    counter!("contest_problem_client_calls_total", "operation" => operation).increment(1);
+    if result.is_err() {
+        counter!("contest_problem_client_errors_total", "operation" => operation).increment(1);
+    }
+    result
+}
+
+/// A thin wrapper over any [`ContestProblemClient`] that records a
+/// per-operation timing histogram and call/error counters (via the `metrics`
+/// crate facade), so a Prometheus exporter can surface p99 latencies and
+/// error rates without changing call sites beyond swapping the concrete type.
+pub struct MeasuredContestProblemClient<'a, C: ContestProblemClient> {
+    inner: &'a C,
+}
+
+impl<'a, C: ContestProblemClient> MeasuredContestProblemClient<'a, C> {
+    pub fn new(inner: &'a C) -> Self {
+        Self { inner }
+    }
+}
+
+impl<'a, C: ContestProblemClient> ContestProblemClient for MeasuredContestProblemClient<'a, C> {
+    fn insert_contest_problem(&self, contest_problems: &[ContestProblem]) -> QueryResult<usize> {
+        with_metrics("insert_contest_problem", || {
+            self.inner.insert_contest_problem(contest_problems)
+        })
+    }
+
+    fn load_contest_problem(&self) -> QueryResult<Vec<ContestProblem>> {
+        with_metrics("load_contest_problem", || self.inner.load_contest_problem())
+    }
+}